@@ -1,5 +1,9 @@
+use std::time::Duration;
+
 pub const USER_MAPPING_TOPIC_SUFFIX: &str = "user/mapping";
 pub const NODE_CONFIG_TOPIC_SUFFIX: &str = "config";
 pub const NODE_PARAMS_LOCAL_INIT_TOPIC_SUFFIX: &str = "params/local/init";
 pub const NODE_PARAMS_REMOTE_TOPIC_SUFFIX: &str = "params/remote";
 pub const NODE_PARAMS_LOCAL_TOPIC_SUFFIX: &str = "params/local";
+
+pub const MQTT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum RmakerMqttError {
     #[error("already started")]
     AlreadyInitialized,
@@ -8,6 +9,12 @@ pub enum RmakerMqttError {
     NodeCredentialsNotFound,
     #[error("not initialized")]
     NotInitialized,
+    #[error("operation timed out")]
+    Timeout,
+    #[error("invalid topic: {0}")]
+    InvalidTopic(String),
+    #[error("payload too large: {0} bytes")]
+    PayloadTooLarge(usize),
     #[error("unknown error")]
     OtherError,
 }
@@ -34,3 +41,11 @@ pub enum RmakerFactoryError {
     #[error("value read error")]
     ValueReadError,
 }
+
+#[derive(Error, Debug)]
+pub(crate) enum RmakerProtoError {
+    #[error("protobuf encode error: {0}")]
+    Encode(quick_protobuf::Error),
+    #[error("protobuf decode error: {0}")]
+    Decode(quick_protobuf::Error),
+}
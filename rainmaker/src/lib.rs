@@ -21,7 +21,6 @@ use constants::*;
 use error::RmakerError;
 use node::Node;
 use proto::esp_rmaker_user_mapping::*;
-use quick_protobuf::{MessageWrite, Writer};
 // expose rainmaker_components crate for use in downstream crates
 pub use rainmaker_components as components;
 use rainmaker_components::{
@@ -32,8 +31,6 @@ use serde_json::{json, Value};
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex, OnceLock},
-    thread,
-    time::Duration,
 };
 
 #[cfg(target_os = "linux")]
@@ -112,23 +109,28 @@ impl Rainmaker {
 
         let curr_node = &self.node;
         let node_id = self.get_node_id();
-        let node_config_topic = format!("node/{}/{}", node_id, NODE_CONFIG_TOPIC_SUFFIX);
-        let params_local_init_topic =
-            format!("node/{}/{}", node_id, NODE_PARAMS_LOCAL_INIT_TOPIC_SUFFIX);
-        let remote_param_topic = format!("node/{}/{}", node_id, NODE_PARAMS_REMOTE_TOPIC_SUFFIX);
+        let remote_param_topic = rmaker_mqtt::node_topic(node_id, NODE_PARAMS_REMOTE_TOPIC_SUFFIX);
 
         match curr_node {
             Some(node) => {
+                // publish directly once connected instead of queueing
+                if rmaker_mqtt::wait_for_connection(MQTT_CONNECT_TIMEOUT).is_err() {
+                    log::warn!("mqtt not connected yet. publishing and subscribing once connected");
+                }
+
                 let node_config = serde_json::to_string(node.as_ref()).unwrap();
                 log::info!("publishing nodeconfig: {}", node_config);
-                rmaker_mqtt::publish(&node_config_topic, node_config.into())?;
+                rmaker_mqtt::publish_node(node_id, NODE_CONFIG_TOPIC_SUFFIX, node_config.into())?;
 
                 let init_params = node.get_param_values();
                 let init_params = serde_json::to_string(&init_params).unwrap();
                 log::info!("publishing initial params: {}", init_params);
-                rmaker_mqtt::publish(&params_local_init_topic, init_params.into())?;
+                rmaker_mqtt::publish_node(
+                    node_id,
+                    NODE_PARAMS_LOCAL_INIT_TOPIC_SUFFIX,
+                    init_params.into(),
+                )?;
                 let node = node.clone();
-                thread::sleep(Duration::from_secs(1)); // wait for connection
                 rmaker_mqtt::subscribe(&remote_param_topic, move |msg| {
                     remote_params_callback(msg, &node)
                 })?
@@ -229,12 +231,23 @@ fn remote_params_callback(msg: ReceivedMessage, node: &Arc<Node>) {
 }
 
 fn cloud_user_assoc_callback(_ep: &str, data: &[u8], node_id: &str) -> Vec<u8> {
-    let req_proto = RMakerConfigPayload::try_from(data).unwrap();
+    let req_proto: RMakerConfigPayload = match proto::decode(data) {
+        Ok(req) => req,
+        Err(e) => {
+            log::error!("could not decode user mapping request: {}", e);
+            return user_mapping_response(RMakerConfigStatus::InvalidParam, node_id);
+        }
+    };
     let req_payload = req_proto.payload;
 
     let (user_id, secret_key) = match req_payload {
         mod_RMakerConfigPayload::OneOfpayload::cmd_set_user_mapping(p) => (p.UserID, p.SecretKey),
-        _ => unreachable!(),
+        // unexpected variants, and payloads newer apps send with unknown oneof fields (decoded
+        // as None), are rejected instead of crashing the node
+        other => {
+            log::error!("unsupported user mapping payload: {:?}", other);
+            return user_mapping_response(RMakerConfigStatus::InvalidParam, node_id);
+        }
     };
 
     log::info!("received user_id={}, secret_key={}", user_id, secret_key);
@@ -246,15 +259,14 @@ fn cloud_user_assoc_callback(_ep: &str, data: &[u8], node_id: &str) -> Vec<u8> {
         "reset": true
     });
 
-    let user_mapping_topic = format!("node/{}/{}", node_id, USER_MAPPING_TOPIC_SUFFIX);
-
     if !rmaker_mqtt::is_mqtt_initialized() && rmaker_mqtt::init_rmaker_mqtt().is_err() {
         // cannot publish user mapping payload
         return vec![0];
     }
 
-    if rmaker_mqtt::publish(
-        &user_mapping_topic,
+    if rmaker_mqtt::publish_node(
+        node_id,
+        USER_MAPPING_TOPIC_SUFFIX,
         user_mapping_json.to_string().as_bytes().to_vec(),
     )
     .is_err()
@@ -262,20 +274,22 @@ fn cloud_user_assoc_callback(_ep: &str, data: &[u8], node_id: &str) -> Vec<u8> {
         log::error!("could not publish user mapping payload");
     }
 
+    user_mapping_response(RMakerConfigStatus::Success, node_id)
+}
+
+fn user_mapping_response(status: RMakerConfigStatus, node_id: &str) -> Vec<u8> {
     let res_proto = RMakerConfigPayload {
         msg: RMakerConfigMsgType::TypeRespSetUserMapping,
         payload: mod_RMakerConfigPayload::OneOfpayload::resp_set_user_mapping(RespSetUserMapping {
-            Status: RMakerConfigStatus::Success,
+            Status: status,
             NodeId: node_id.to_string(),
         }),
     };
 
-    let mut out_vec = vec![];
-    let mut writer = Writer::new(&mut out_vec);
-
-    res_proto.write_message(&mut writer).unwrap();
-
-    out_vec
+    proto::encode(&res_proto).unwrap_or_else(|e| {
+        log::error!("could not encode user mapping response: {}", e);
+        vec![]
+    })
 }
 
 /// Reports parameters values of devices to the RainMaker cloud over MQTT.
@@ -299,6 +313,51 @@ pub fn report_params(device_name: &str, params: HashMap<String, Value>) {
     // TODO: cache this value somewhere?
     let mut buff = [0u8; 32];
     let node_id = factory::get_node_id(&mut buff).unwrap();
-    let local_params_topic = format!("node/{}/{}", node_id, NODE_PARAMS_LOCAL_TOPIC_SUFFIX);
-    rmaker_mqtt::publish(&local_params_topic, updated_params.to_string().into_bytes()).unwrap();
+    if let Err(e) = rmaker_mqtt::publish_node(
+        &node_id,
+        NODE_PARAMS_LOCAL_TOPIC_SUFFIX,
+        updated_params.to_string().into_bytes(),
+    ) {
+        log::error!("could not report params: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response_status(res: &[u8]) -> RMakerConfigStatus {
+        let res_proto: RMakerConfigPayload = proto::decode(res).unwrap();
+        match res_proto.payload {
+            mod_RMakerConfigPayload::OneOfpayload::resp_set_user_mapping(r) => r.Status,
+            other => panic!("unexpected response payload: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn user_assoc_rejects_unsupported_payload() {
+        let req_proto = RMakerConfigPayload {
+            msg: RMakerConfigMsgType::TypeRespSetUserMapping,
+            payload: mod_RMakerConfigPayload::OneOfpayload::resp_set_user_mapping(
+                RespSetUserMapping::default(),
+            ),
+        };
+        let req = proto::encode(&req_proto).unwrap();
+
+        let res = cloud_user_assoc_callback("cloud_user_assoc", &req, "node_id");
+        assert_eq!(response_status(&res), RMakerConfigStatus::InvalidParam);
+    }
+
+    #[test]
+    fn user_assoc_rejects_unknown_payload_field() {
+        // field 12 (length-delimited, empty) is not known to this version of the proto
+        let res = cloud_user_assoc_callback("cloud_user_assoc", &[0x62, 0x00], "node_id");
+        assert_eq!(response_status(&res), RMakerConfigStatus::InvalidParam);
+    }
+
+    #[test]
+    fn user_assoc_rejects_malformed_payload() {
+        let res = cloud_user_assoc_callback("cloud_user_assoc", &[0xff, 0xff], "node_id");
+        assert_eq!(response_status(&res), RMakerConfigStatus::InvalidParam);
+    }
 }
@@ -1 +1,21 @@
 pub mod esp_rmaker_user_mapping;
+
+use quick_protobuf::{BytesReader, MessageRead, MessageWrite, Writer};
+
+use crate::error::RmakerProtoError;
+
+/// Encodes a protobuf message into a byte vector.
+pub(crate) fn encode<M: MessageWrite>(msg: &M) -> Result<Vec<u8>, RmakerProtoError> {
+    let mut out_vec = vec![];
+    let mut writer = Writer::new(&mut out_vec);
+    msg.write_message(&mut writer)
+        .map_err(RmakerProtoError::Encode)?;
+
+    Ok(out_vec)
+}
+
+/// Decodes a protobuf message from bytes.
+pub(crate) fn decode<'a, M: MessageRead<'a>>(bytes: &'a [u8]) -> Result<M, RmakerProtoError> {
+    let mut reader = BytesReader::from_bytes(bytes);
+    M::from_reader(&mut reader, bytes).map_err(RmakerProtoError::Decode)
+}
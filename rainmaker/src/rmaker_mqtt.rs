@@ -1,13 +1,19 @@
 use std::{
     collections::HashMap,
-    sync::{atomic::AtomicBool, LazyLock, OnceLock, RwLock},
+    sync::{atomic::AtomicBool, Condvar, LazyLock, Mutex, OnceLock, RwLock},
+    time::Duration,
 };
 
 use rainmaker_components::mqtt::{
     MqttClient, MqttConfiguration, MqttEvent, QoSLevel, ReceivedMessage, TLSconfiguration,
 };
 
-use crate::{error::RmakerMqttError, factory, utils::wrap_in_arc_mutex, WrappedInArcMutex};
+use crate::{
+    error::RmakerMqttError,
+    factory,
+    utils::{lock_recover, recover, wrap_in_arc_mutex},
+    WrappedInArcMutex,
+};
 
 pub(crate) trait TopicCb = Fn(ReceivedMessage) + Sync + Send + 'static;
 static MQTT_INNER: OnceLock<WrappedInArcMutex<MqttClient>> = OnceLock::new();
@@ -16,6 +22,14 @@ static MQTT_CBS: LazyLock<RwLock<HashMap<String, Box<dyn TopicCb>>>> =
 static PUBLISH_QUEUE: LazyLock<RwLock<HashMap<String, Vec<u8>>>> =
     LazyLock::new(|| RwLock::new(HashMap::new())); // topic -> payload
 static CONNECTED: AtomicBool = AtomicBool::new(false);
+// notified when the client connects, used by wait_for_connection
+static CONNECTED_LOCK: Mutex<()> = Mutex::new(());
+static CONNECTED_CVAR: Condvar = Condvar::new();
+
+// maximum length of a topic name allowed by MQTT spec
+const MQTT_MAX_TOPIC_LEN: usize = 65535;
+// maximum payload size accepted by AWS IoT Core
+const MQTT_MAX_PAYLOAD_LEN: usize = 128 * 1024;
 
 pub(crate) fn init_rmaker_mqtt() -> Result<(), RmakerMqttError> {
     // return error if mqtt is already initialized
@@ -69,12 +83,31 @@ pub(crate) fn is_mqtt_initialized() -> bool {
     MQTT_INNER.get().is_some()
 }
 
-// this function is not used right now but may be required in future
-#[allow(dead_code)]
 pub(crate) fn is_mqtt_connected() -> bool {
     CONNECTED.load(std::sync::atomic::Ordering::SeqCst)
 }
 
+/// Blocks until the client is connected to the broker.
+///
+/// Returns [RmakerMqttError::Timeout] if the connection is not established within `timeout`.
+pub(crate) fn wait_for_connection(timeout: Duration) -> Result<(), RmakerMqttError> {
+    if !is_mqtt_initialized() {
+        return Err(RmakerMqttError::NotInitialized);
+    }
+
+    let guard = lock_recover(&CONNECTED_LOCK);
+    let (_guard, res) = recover(
+        &CONNECTED_LOCK,
+        CONNECTED_CVAR.wait_timeout_while(guard, timeout, |_| !is_mqtt_connected()),
+    );
+
+    if res.timed_out() {
+        return Err(RmakerMqttError::Timeout);
+    }
+
+    Ok(())
+}
+
 fn mqtt_callback(event: MqttEvent) {
     match event {
         MqttEvent::Received(msg) => {
@@ -87,7 +120,11 @@ fn mqtt_callback(event: MqttEvent) {
 
         MqttEvent::Connected => {
             CONNECTED.store(true, std::sync::atomic::Ordering::SeqCst);
-            let mut mqtt = MQTT_INNER.get().unwrap().lock().unwrap();
+            {
+                let _guard = lock_recover(&CONNECTED_LOCK);
+                CONNECTED_CVAR.notify_all();
+            }
+            let mut mqtt = lock_recover(MQTT_INNER.get().unwrap());
             for topic in MQTT_CBS.read().unwrap().keys() {
                 if mqtt.subscribe(topic, &QoSLevel::AtLeastOnce).is_err() {
                     log::error!("could not subscribe to {}", topic)
@@ -125,14 +162,26 @@ pub(crate) fn connect(
     Err(RmakerMqttError::OtherError)
 }
 
+fn validate_publish(topic: &str, payload: &[u8]) -> Result<(), RmakerMqttError> {
+    // wildcards are only allowed in topic filters used for subscribing
+    if topic.is_empty() || topic.len() > MQTT_MAX_TOPIC_LEN || topic.contains(['+', '#', '\0']) {
+        return Err(RmakerMqttError::InvalidTopic(topic.to_owned()));
+    }
+
+    if payload.len() > MQTT_MAX_PAYLOAD_LEN {
+        return Err(RmakerMqttError::PayloadTooLarge(payload.len()));
+    }
+
+    Ok(())
+}
+
 pub(crate) fn publish(topic: &str, payload: Vec<u8>) -> Result<(), RmakerMqttError> {
+    validate_publish(topic, &payload)?;
+
     match MQTT_INNER.get() {
         Some(client) => {
             if CONNECTED.load(std::sync::atomic::Ordering::SeqCst) {
-                client
-                    .lock()
-                    .unwrap()
-                    .publish(topic, &QoSLevel::AtLeastOnce, payload);
+                lock_recover(client).publish(topic, &QoSLevel::AtLeastOnce, payload);
             } else {
                 // mqtt is not connected. store to publish when connected
                 log::info!("mqtt not connected. queueing message");
@@ -150,13 +199,25 @@ pub(crate) fn publish(topic: &str, payload: Vec<u8>) -> Result<(), RmakerMqttErr
     Ok(())
 }
 
+/// Builds the full topic for a RainMaker node, i.e. `node/<node_id>/<suffix>`.
+pub(crate) fn node_topic(node_id: &str, suffix: &str) -> String {
+    format!("node/{}/{}", node_id, suffix)
+}
+
+/// Publishes `payload` on the topic `node/<node_id>/<suffix>`.
+pub(crate) fn publish_node(
+    node_id: &str,
+    suffix: &str,
+    payload: Vec<u8>,
+) -> Result<(), RmakerMqttError> {
+    publish(&node_topic(node_id, suffix), payload)
+}
+
 pub(crate) fn subscribe(topic: &str, cb: impl TopicCb) -> Result<(), RmakerMqttError> {
     match MQTT_INNER.get() {
         Some(client) => {
             if CONNECTED.load(std::sync::atomic::Ordering::SeqCst)
-                && client
-                    .lock()
-                    .unwrap()
+                && lock_recover(client)
                     .subscribe(topic, &QoSLevel::AtLeastOnce)
                     .is_err()
             {
@@ -175,3 +236,39 @@ pub(crate) fn subscribe(topic: &str, cb: impl TopicCb) -> Result<(), RmakerMqttE
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_publish_rejects_invalid_topics() {
+        for topic in ["", "node/+/params", "node/#", "node/\0/params"] {
+            assert!(matches!(
+                validate_publish(topic, &[]),
+                Err(RmakerMqttError::InvalidTopic(_))
+            ));
+        }
+
+        let long_topic = "a".repeat(MQTT_MAX_TOPIC_LEN + 1);
+        assert!(matches!(
+            validate_publish(&long_topic, &[]),
+            Err(RmakerMqttError::InvalidTopic(_))
+        ));
+    }
+
+    #[test]
+    fn validate_publish_rejects_large_payload() {
+        let payload = vec![0; MQTT_MAX_PAYLOAD_LEN + 1];
+        assert!(matches!(
+            validate_publish("node/abc/params/local", &payload),
+            Err(RmakerMqttError::PayloadTooLarge(len)) if len == MQTT_MAX_PAYLOAD_LEN + 1
+        ));
+    }
+
+    #[test]
+    fn validate_publish_accepts_node_topic() {
+        let payload = vec![0; MQTT_MAX_PAYLOAD_LEN];
+        assert!(validate_publish("node/abc/params/local", &payload).is_ok());
+    }
+}
@@ -1,5 +1,5 @@
 // utility types and functions for using throughout the crate
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, LockResult, Mutex, MutexGuard};
 
 pub(crate) type WrappedInArcMutex<T> = Arc<Mutex<T>>;
 
@@ -7,3 +7,21 @@ pub(crate) type WrappedInArcMutex<T> = Arc<Mutex<T>>;
 pub(crate) fn wrap_in_arc_mutex<T>(inp: T) -> WrappedInArcMutex<T> {
     Arc::new(Mutex::new(inp))
 }
+
+/// Locks the mutex, recovering the guard instead of panicking if the mutex was poisoned.
+///
+/// The poison flag is cleared on recovery so subsequent locks succeed normally.
+pub(crate) fn lock_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    recover(mutex, mutex.lock())
+}
+
+/// Takes the guard out of a result returned by an operation on `mutex` (lock, condvar wait),
+/// logging a warning and clearing the poison flag if the mutex was poisoned.
+pub(crate) fn recover<T, G>(mutex: &Mutex<T>, res: LockResult<G>) -> G {
+    res.unwrap_or_else(|poisoned| {
+        log::warn!("mutex was poisoned. recovering");
+        let guard = poisoned.into_inner();
+        mutex.clear_poison();
+        guard
+    })
+}